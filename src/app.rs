@@ -1,25 +1,42 @@
 use anyhow::{anyhow, Result};
-use async_std::task;
+use async_std::{net::TcpStream, prelude::*, task};
 use core::ops::Range;
-use futures::{future::Either, pin_mut, FutureExt};
+use egui_dock::{DockState, NodeIndex};
+use futures::{pin_mut, FutureExt};
 use huelib::{bridge, bridge::Bridge, resource::light::StateModifier, resource::Light, Color};
+use notify::Watcher;
+use scrap::{Capturer, Display};
 use serde::{Deserialize, Serialize};
 use std::{
     collections::HashMap,
+    future::Future,
     net::IpAddr,
-    sync::{mpsc, Arc, RwLock},
+    path::{Path, PathBuf},
+    pin::Pin,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        mpsc, Arc, RwLock,
+    },
     time::Duration,
 };
 
 static APP_NAME: &str = "kinderdisco";
+static REMOTE_CONTROL_ADDR: &str = "127.0.0.1:4747";
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum SyncMode {
     None,
     Time,
     TimeAndColor,
+    Ambient,
 }
-#[derive(Clone)]
+
+impl Default for SyncMode {
+    fn default() -> Self {
+        Self::None
+    }
+}
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Data {
     pub r: Range<u8>,
     pub g: Range<u8>,
@@ -40,17 +57,78 @@ impl Default for Data {
     }
 }
 
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Preset {
+    pub name: String,
+    pub display_name: String,
+    pub description: String,
+    pub data: Data,
+    pub sync_mode: SyncMode,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", content = "data")]
+enum RemoteCommand {
+    SetRanges {
+        r: Range<u8>,
+        g: Range<u8>,
+        b: Range<u8>,
+        time: Range<u16>,
+    },
+    SetFade(bool),
+    SetSyncMode(SyncMode),
+    ToggleLight {
+        unique_id: String,
+        on: bool,
+    },
+    Trigger,
+}
+
+#[derive(Serialize)]
+struct RemoteLight {
+    unique_id: String,
+    name: String,
+    on: bool,
+}
+
+#[derive(Serialize, Default)]
+struct RemoteStatus {
+    data: Data,
+    sync_mode: SyncMode,
+    lights: Vec<RemoteLight>,
+}
+
 pub struct DiscoLight {
     pub light: Light,
     pub on: bool,
+    pub beat_division: u32,
+    pub last_color: Option<(u8, u8, u8)>,
 }
 
 impl DiscoLight {
     pub fn new(light: Light) -> Self {
-        Self { light, on: false }
+        Self {
+            light,
+            on: false,
+            beat_division: 1,
+            last_color: None,
+        }
+    }
+}
+
+fn normalize_range<T: Ord>(range: Range<T>) -> Range<T> {
+    if range.start <= range.end {
+        range
+    } else {
+        range.end..range.start
     }
 }
 
+fn normalize_time_range(range: Range<u16>) -> Range<u16> {
+    let range = normalize_range(range);
+    range.start.clamp(1, 100)..range.end.clamp(1, 100)
+}
+
 fn rand_range<S, T>(rng: &mut S, range: &core::ops::Range<T>) -> T
 where
     S: random::Source,
@@ -69,7 +147,12 @@ where
     }
 }
 
-async fn modify_lights_same_color(bridge: Bridge, light_ids: Vec<String>, data: Arc<RwLock<Data>>) {
+async fn modify_lights_same_color(
+    bridge: Bridge,
+    light_ids: Vec<(String, String)>,
+    data: Arc<RwLock<Data>>,
+    sender: mpsc::Sender<Signal>,
+) {
     let mut rng = random::default(43);
     loop {
         let time;
@@ -77,16 +160,122 @@ async fn modify_lights_same_color(bridge: Bridge, light_ids: Vec<String>, data:
             let data = data.read().unwrap();
             time = rand_range(&mut rng, &data.time);
             let transition_time = if data.fade { time } else { 0 };
+            let rgb = (
+                rand_range(&mut rng, &data.r),
+                rand_range(&mut rng, &data.g),
+                rand_range(&mut rng, &data.b),
+            );
             let modifier = StateModifier::new()
                 .with_on(true)
-                .with_color(Color::from_rgb(
-                    rand_range(&mut rng, &data.r),
-                    rand_range(&mut rng, &data.g),
-                    rand_range(&mut rng, &data.b),
-                ))
+                .with_color(Color::from_rgb(rgb.0, rgb.1, rgb.2))
                 .with_transition_time(transition_time);
-            for light_id in &light_ids {
+            for (light_id, unique_id) in &light_ids {
                 _ = bridge.set_light_state(light_id, &modifier);
+                _ = sender.send(Signal::LightColor {
+                    unique_id: unique_id.clone(),
+                    rgb,
+                });
+            }
+        }
+        task::sleep(Duration::from_millis(time as u64 * 100)).await;
+    }
+}
+
+fn clamp_channel(value: u8, range: &Range<u8>) -> u8 {
+    value.clamp(range.start, range.end.max(range.start))
+}
+
+async fn capture_frame(capturer: &mut Capturer) -> Option<(Vec<u8>, usize, usize)> {
+    let width = capturer.width();
+    let height = capturer.height();
+    loop {
+        match capturer.frame() {
+            Ok(frame) => return Some((frame.to_vec(), width, height)),
+            Err(ref e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                task::sleep(Duration::from_millis(10)).await;
+            }
+            Err(_) => return None,
+        }
+    }
+}
+
+async fn modify_lights_ambient(
+    bridge: Bridge,
+    light_ids: Vec<(String, String)>,
+    data: Arc<RwLock<Data>>,
+    sender: mpsc::Sender<Signal>,
+) {
+    let zone_count = light_ids.len().max(1);
+    let mut rng = random::default(
+        light_ids
+            .first()
+            .map(|(id, _)| id.clone())
+            .unwrap_or_else(|| "42".to_string())
+            .parse::<u64>()
+            .unwrap_or(42),
+    );
+    let display = match Display::primary() {
+        Ok(display) => display,
+        Err(_) => return,
+    };
+    let mut capturer = match Capturer::new(display) {
+        Ok(capturer) => capturer,
+        Err(_) => return,
+    };
+    let alpha = 0.3f32;
+    let mut smoothed = vec![(0.0f32, 0.0f32, 0.0f32); zone_count];
+    loop {
+        let time;
+        {
+            let data = data.read().unwrap();
+            time = rand_range(&mut rng, &data.time);
+            let transition_time = if data.fade { time } else { 0 };
+            if let Some((buffer, width, height)) = capture_frame(&mut capturer).await {
+                let stride = buffer.len() / height.max(1);
+                let zone_height = (height / zone_count).max(1);
+                for (zone, (light_id, unique_id)) in light_ids.iter().enumerate() {
+                    let start_row = zone * zone_height;
+                    let end_row = if zone == zone_count - 1 {
+                        height
+                    } else {
+                        (start_row + zone_height).min(height)
+                    };
+                    let (mut sum_r, mut sum_g, mut sum_b, mut count) = (0u64, 0u64, 0u64, 0u64);
+                    for row in start_row..end_row {
+                        for col in 0..width {
+                            let offset = row * stride + col * 4;
+                            sum_b += buffer[offset] as u64;
+                            sum_g += buffer[offset + 1] as u64;
+                            sum_r += buffer[offset + 2] as u64;
+                            count += 1;
+                        }
+                    }
+                    if count == 0 {
+                        continue;
+                    }
+                    let (prev_r, prev_g, prev_b) = smoothed[zone];
+                    let sample_r = sum_r as f32 / count as f32;
+                    let sample_g = sum_g as f32 / count as f32;
+                    let sample_b = sum_b as f32 / count as f32;
+                    let new_r = alpha * sample_r + (1.0 - alpha) * prev_r;
+                    let new_g = alpha * sample_g + (1.0 - alpha) * prev_g;
+                    let new_b = alpha * sample_b + (1.0 - alpha) * prev_b;
+                    smoothed[zone] = (new_r, new_g, new_b);
+                    let rgb = (
+                        clamp_channel(new_r as u8, &data.r),
+                        clamp_channel(new_g as u8, &data.g),
+                        clamp_channel(new_b as u8, &data.b),
+                    );
+                    let modifier = StateModifier::new()
+                        .with_on(true)
+                        .with_color(Color::from_rgb(rgb.0, rgb.1, rgb.2))
+                        .with_transition_time(transition_time);
+                    _ = bridge.set_light_state(light_id, &modifier);
+                    _ = sender.send(Signal::LightColor {
+                        unique_id: unique_id.clone(),
+                        rgb,
+                    });
+                }
             }
         }
         task::sleep(Duration::from_millis(time as u64 * 100)).await;
@@ -95,13 +284,15 @@ async fn modify_lights_same_color(bridge: Bridge, light_ids: Vec<String>, data:
 
 async fn modify_lights_different_colors(
     bridge: Bridge,
-    light_ids: Vec<String>,
+    light_ids: Vec<(String, String)>,
     data: Arc<RwLock<Data>>,
+    sender: mpsc::Sender<Signal>,
 ) {
     let mut rng = random::default(
         light_ids
             .first()
-            .unwrap_or(&42.to_string())
+            .map(|(id, _)| id.clone())
+            .unwrap_or_else(|| "42".to_string())
             .parse::<u64>()
             .unwrap_or(42),
     );
@@ -111,37 +302,100 @@ async fn modify_lights_different_colors(
             let data = data.read().unwrap();
             time = rand_range(&mut rng, &data.time);
             let transition_time = if data.fade { time } else { 0 };
-            for light_id in &light_ids {
+            for (light_id, unique_id) in &light_ids {
+                let rgb = (
+                    rand_range(&mut rng, &data.r),
+                    rand_range(&mut rng, &data.g),
+                    rand_range(&mut rng, &data.b),
+                );
                 let modifier = StateModifier::new()
                     .with_on(true)
-                    .with_color(Color::from_rgb(
-                        rand_range(&mut rng, &data.r),
-                        rand_range(&mut rng, &data.g),
-                        rand_range(&mut rng, &data.b),
-                    ))
+                    .with_color(Color::from_rgb(rgb.0, rgb.1, rgb.2))
                     .with_transition_time(transition_time);
                 _ = bridge.set_light_state(light_id, &modifier);
+                _ = sender.send(Signal::LightColor {
+                    unique_id: unique_id.clone(),
+                    rgb,
+                });
             }
         }
         task::sleep(Duration::from_millis(time as u64 * 100)).await;
     }
 }
+async fn modify_lights_clocked(
+    bridge: Bridge,
+    light_id: String,
+    unique_id: String,
+    data: Arc<RwLock<Data>>,
+    clock: Arc<AtomicU64>,
+    divisor: u32,
+    sender: mpsc::Sender<Signal>,
+) {
+    let mut rng = random::default(light_id.parse::<u64>().unwrap_or(42));
+    let divisor = divisor.max(1) as u64;
+    let mut last_seen = clock.load(Ordering::SeqCst);
+    loop {
+        let current = clock.load(Ordering::SeqCst);
+        if current != last_seen {
+            last_seen = current;
+            if current % divisor == 0 {
+                let data = data.read().unwrap();
+                let transition_time = if data.fade {
+                    rand_range(&mut rng, &data.time)
+                } else {
+                    0
+                };
+                let rgb = (
+                    rand_range(&mut rng, &data.r),
+                    rand_range(&mut rng, &data.g),
+                    rand_range(&mut rng, &data.b),
+                );
+                let modifier = StateModifier::new()
+                    .with_on(true)
+                    .with_color(Color::from_rgb(rgb.0, rgb.1, rgb.2))
+                    .with_transition_time(transition_time);
+                _ = bridge.set_light_state(&light_id, &modifier);
+                _ = sender.send(Signal::LightColor {
+                    unique_id: unique_id.clone(),
+                    rgb,
+                });
+            }
+        }
+        task::sleep(Duration::from_millis(20)).await;
+    }
+}
+
 struct Modulator(futures::channel::oneshot::Sender<()>);
 
 impl Modulator {
     fn new(
         sync_mode: SyncMode,
-        light_ids: Vec<String>,
+        light_ids: Vec<(String, String)>,
         bridge: Bridge,
         data: Arc<RwLock<Data>>,
+        signal_sender: mpsc::Sender<Signal>,
     ) -> Self {
         let (sender, receiver) = futures::channel::oneshot::channel::<()>();
         task::spawn(async move {
-            let task = match sync_mode {
-                SyncMode::TimeAndColor => {
-                    Either::Left(modify_lights_same_color(bridge, light_ids, data))
-                }
-                _ => Either::Right(modify_lights_different_colors(bridge, light_ids, data)),
+            let task: Pin<Box<dyn Future<Output = ()> + Send>> = match sync_mode {
+                SyncMode::TimeAndColor => Box::pin(modify_lights_same_color(
+                    bridge,
+                    light_ids,
+                    data,
+                    signal_sender,
+                )),
+                SyncMode::Ambient => Box::pin(modify_lights_ambient(
+                    bridge,
+                    light_ids,
+                    data,
+                    signal_sender,
+                )),
+                _ => Box::pin(modify_lights_different_colors(
+                    bridge,
+                    light_ids,
+                    data,
+                    signal_sender,
+                )),
             };
             let task = task.fuse();
             let receiver = receiver.fuse();
@@ -154,12 +408,81 @@ impl Modulator {
         });
         Self(sender)
     }
+
+    fn new_clocked(
+        light_id: String,
+        unique_id: String,
+        bridge: Bridge,
+        data: Arc<RwLock<Data>>,
+        clock: Arc<AtomicU64>,
+        divisor: u32,
+        signal_sender: mpsc::Sender<Signal>,
+    ) -> Self {
+        let (sender, receiver) = futures::channel::oneshot::channel::<()>();
+        task::spawn(async move {
+            let task = modify_lights_clocked(
+                bridge,
+                light_id,
+                unique_id,
+                data,
+                clock,
+                divisor,
+                signal_sender,
+            )
+            .fuse();
+            let receiver = receiver.fuse();
+            pin_mut!(task);
+            pin_mut!(receiver);
+            futures::select! {
+            _ = receiver => (),
+            _ = task => (),
+            };
+        });
+        Self(sender)
+    }
+}
+
+struct ClockHandle {
+    tick: Arc<AtomicU64>,
+    _cancel: futures::channel::oneshot::Sender<()>,
+}
+
+impl ClockHandle {
+    fn new(interval: u16) -> Self {
+        let tick = Arc::new(AtomicU64::new(0));
+        let (cancel, receiver) = futures::channel::oneshot::channel::<()>();
+        let tick_clone = tick.clone();
+        task::spawn(async move {
+            let receiver = receiver.fuse();
+            pin_mut!(receiver);
+            loop {
+                let sleep = task::sleep(Duration::from_millis(interval.max(1) as u64 * 100)).fuse();
+                pin_mut!(sleep);
+                futures::select! {
+                    _ = receiver => break,
+                    _ = sleep => {
+                        _ = tick_clone.fetch_add(1, Ordering::SeqCst);
+                    }
+                }
+            }
+        });
+        Self {
+            tick,
+            _cancel: cancel,
+        }
+    }
 }
 
 enum Signal {
     Ip(Option<IpAddr>),
     Bridge(Option<Bridge>),
     Lights(Vec<Light>),
+    Presets(Vec<Preset>),
+    Remote(RemoteCommand, futures::channel::oneshot::Sender<()>),
+    LightColor {
+        unique_id: String,
+        rgb: (u8, u8, u8),
+    },
     Error(String),
 }
 
@@ -175,10 +498,24 @@ pub struct App {
     pub sync_mode: SyncMode,
     modulators: Vec<Modulator>,
     pub rebuild_modulators: bool,
+    pub presets: Vec<Preset>,
+    pub selected_preset: Option<String>,
+    pub new_preset_name: String,
+    presets_dir: Option<PathBuf>,
+    remote_status: Arc<RwLock<RemoteStatus>>,
+    clock: ClockHandle,
+    clock_interval: u16,
+    pub dock_state: DockState<String>,
 }
 
 impl Default for App {
     fn default() -> Self {
+        let clock_interval = Data::default().time.start;
+        let mut dock_state = DockState::new(vec!["Controls".to_string()]);
+        let surface = dock_state.main_surface_mut();
+        let [_, lights_node] =
+            surface.split_right(NodeIndex::root(), 0.6, vec!["Lights".to_string()]);
+        surface.split_below(lights_node, 0.6, vec!["Preview".to_string()]);
         Self {
             ip: None,
             user: None,
@@ -191,6 +528,14 @@ impl Default for App {
             sync_mode: SyncMode::None,
             modulators: vec![],
             rebuild_modulators: false,
+            presets: vec![],
+            selected_preset: None,
+            new_preset_name: String::new(),
+            presets_dir: None,
+            remote_status: Arc::new(RwLock::new(RemoteStatus::default())),
+            clock: ClockHandle::new(clock_interval),
+            clock_interval,
+            dock_state,
         }
     }
 }
@@ -226,11 +571,155 @@ impl App {
                         })
                         .collect::<Vec<_>>();
                 }
+                Signal::Presets(presets) => self.presets = presets,
+                Signal::Remote(command, ack) => {
+                    self.handle_remote_command(command);
+                    self.sync_remote_status();
+                    _ = ack.send(());
+                }
+                Signal::LightColor { unique_id, rgb } => {
+                    if let Some(light) = self.lights.get_mut(&unique_id) {
+                        light.last_color = Some(rgb);
+                    }
+                }
                 Signal::Error(e) => self.error = Some(e),
             }
         }
     }
 
+    fn sync_remote_status(&self) {
+        let mut remote_status = self.remote_status.write().unwrap();
+        remote_status.data = self.data.clone();
+        remote_status.sync_mode = self.sync_mode;
+        remote_status.lights = self
+            .lights
+            .values()
+            .map(|light| RemoteLight {
+                unique_id: light.light.unique_id.clone(),
+                name: light.light.name.clone(),
+                on: light.on,
+            })
+            .collect();
+    }
+
+    fn handle_remote_command(&mut self, command: RemoteCommand) {
+        match command {
+            RemoteCommand::SetRanges { r, g, b, time } => {
+                self.data.r = normalize_range(r);
+                self.data.g = normalize_range(g);
+                self.data.b = normalize_range(b);
+                self.data.time = normalize_time_range(time);
+            }
+            RemoteCommand::SetFade(fade) => self.data.fade = fade,
+            RemoteCommand::SetSyncMode(sync_mode) => {
+                self.sync_mode = sync_mode;
+                self.rebuild_modulators = true;
+            }
+            RemoteCommand::ToggleLight { unique_id, on } => {
+                if let Some(light) = self.lights.get_mut(&unique_id) {
+                    light.on = on;
+                    self.rebuild_modulators = true;
+                }
+            }
+            RemoteCommand::Trigger => self.trigger_step(),
+        }
+    }
+
+    fn trigger_step(&self) {
+        if let Some(bridge) = self.bridge.clone() {
+            let light_ids = self
+                .lights
+                .values()
+                .filter(|light| light.on)
+                .map(|light| (light.light.id.clone(), light.light.unique_id.clone()))
+                .collect::<Vec<_>>();
+            let data = self.data.clone();
+            let sender = self.channel.0.clone();
+            task::spawn(async move {
+                let mut rng = random::default(42);
+                let transition_time = if data.fade {
+                    rand_range(&mut rng, &data.time)
+                } else {
+                    0
+                };
+                let rgb = (
+                    rand_range(&mut rng, &data.r),
+                    rand_range(&mut rng, &data.g),
+                    rand_range(&mut rng, &data.b),
+                );
+                let modifier = StateModifier::new()
+                    .with_on(true)
+                    .with_color(Color::from_rgb(rgb.0, rgb.1, rgb.2))
+                    .with_transition_time(transition_time);
+                for (light_id, unique_id) in light_ids {
+                    _ = bridge.set_light_state(&light_id, &modifier);
+                    _ = sender.send(Signal::LightColor { unique_id, rgb });
+                }
+            });
+        }
+    }
+
+    pub fn start_remote_control(&mut self) {
+        let sender = self.channel.0.clone();
+        let status = self.remote_status.clone();
+        task::spawn(async move {
+            let listener = match async_std::net::TcpListener::bind(REMOTE_CONTROL_ADDR).await {
+                Ok(listener) => listener,
+                Err(_) => return,
+            };
+            let mut incoming = listener.incoming();
+            while let Some(Ok(stream)) = incoming.next().await {
+                task::spawn(handle_remote_connection(
+                    stream,
+                    sender.clone(),
+                    status.clone(),
+                ));
+            }
+        });
+    }
+
+    pub fn start_presets(&mut self) {
+        if let Some(dir) = presets_dir() {
+            self.presets_dir = Some(dir.clone());
+            self.presets = load_presets(&dir);
+            watch_presets(dir, self.channel.0.clone());
+        }
+    }
+
+    pub fn save_preset(&mut self, display_name: String) {
+        if let Some(dir) = &self.presets_dir {
+            let name = slugify(&display_name);
+            if let Some(existing) = self.presets.iter().find(|preset| preset.name == name) {
+                if existing.display_name != display_name {
+                    self.error = Some(format!(
+                        "A preset named \"{}\" already uses that slug; choose a different name.",
+                        existing.display_name
+                    ));
+                    return;
+                }
+            }
+            let preset = Preset {
+                name,
+                display_name,
+                description: String::new(),
+                data: self.data.clone(),
+                sync_mode: self.sync_mode,
+            };
+            self.selected_preset = Some(preset.name.clone());
+            store_preset(dir, &preset);
+            self.new_preset_name.clear();
+        }
+    }
+
+    pub fn delete_preset(&mut self, name: &str) {
+        if let Some(dir) = &self.presets_dir {
+            delete_preset(dir, name);
+            if self.selected_preset.as_deref() == Some(name) {
+                self.selected_preset = None;
+            }
+        }
+    }
+
     pub fn get_bridge_ip(&mut self) {
         let sender = self.channel.0.clone();
         async_std::task::spawn(async move {
@@ -277,6 +766,15 @@ impl App {
             let mut async_data = self.async_data.write().unwrap();
             *async_data = self.data.clone();
         }
+        self.sync_remote_status();
+
+        if self.data.time.start != self.clock_interval {
+            self.clock_interval = self.data.time.start;
+            self.clock = ClockHandle::new(self.clock_interval);
+            if self.sync_mode == SyncMode::None {
+                self.rebuild_modulators = true;
+            }
+        }
 
         if self.rebuild_modulators {
             self.rebuild_modulators();
@@ -291,26 +789,39 @@ impl App {
                 .lights
                 .iter()
                 .filter(|(_, light)| light.on)
-                .map(|light| light.1.light.id.clone())
+                .map(|(_, light)| {
+                    (
+                        light.light.id.clone(),
+                        light.light.unique_id.clone(),
+                        light.beat_division,
+                    )
+                })
                 .collect::<Vec<_>>();
 
             self.modulators = match self.sync_mode {
                 SyncMode::None => lights
                     .drain(..)
-                    .map(|l| {
-                        Modulator::new(
-                            SyncMode::None,
-                            vec![l],
+                    .map(|(id, unique_id, beat_division)| {
+                        Modulator::new_clocked(
+                            id,
+                            unique_id,
                             bridge.clone(),
                             self.async_data.clone(),
+                            self.clock.tick.clone(),
+                            beat_division,
+                            self.channel.0.clone(),
                         )
                     })
                     .collect::<Vec<_>>(),
                 _ => vec![Modulator::new(
                     self.sync_mode,
-                    lights,
+                    lights
+                        .drain(..)
+                        .map(|(id, unique_id, _)| (id, unique_id))
+                        .collect(),
                     bridge.clone(),
                     self.async_data.clone(),
+                    self.channel.0.clone(),
                 )],
             }
         }
@@ -334,6 +845,98 @@ fn store_user(user: String) {
     _ = confy::store(APP_NAME, None, config);
 }
 
+fn presets_dir() -> Option<PathBuf> {
+    let config_path = confy::get_configuration_file_path(APP_NAME, None).ok()?;
+    let dir = config_path.parent()?.join("presets");
+    std::fs::create_dir_all(&dir).ok()?;
+    Some(dir)
+}
+
+fn slugify(name: &str) -> String {
+    name.to_lowercase()
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '-' })
+        .collect()
+}
+
+fn preset_path(dir: &Path, name: &str) -> PathBuf {
+    dir.join(format!("{}.yaml", name))
+}
+
+fn load_presets(dir: &Path) -> Vec<Preset> {
+    let mut presets = match std::fs::read_dir(dir) {
+        Ok(entries) => entries
+            .flatten()
+            .filter(|entry| entry.path().extension() == Some("yaml".as_ref()))
+            .filter_map(|entry| std::fs::read_to_string(entry.path()).ok())
+            .filter_map(|content| serde_yaml::from_str::<Preset>(&content).ok())
+            .collect::<Vec<_>>(),
+        Err(_) => vec![],
+    };
+    presets.sort_by(|a, b| a.display_name.cmp(&b.display_name));
+    presets
+}
+
+fn store_preset(dir: &Path, preset: &Preset) {
+    if let Ok(yaml) = serde_yaml::to_string(preset) {
+        _ = std::fs::write(preset_path(dir, &preset.name), yaml);
+    }
+}
+
+fn delete_preset(dir: &Path, name: &str) {
+    _ = std::fs::remove_file(preset_path(dir, name));
+}
+
+fn watch_presets(dir: PathBuf, sender: mpsc::Sender<Signal>) {
+    task::spawn(async move {
+        let (watch_sender, watch_receiver) = std::sync::mpsc::channel();
+        let mut watcher = match notify::recommended_watcher(watch_sender) {
+            Ok(watcher) => watcher,
+            Err(_) => return,
+        };
+        if watcher
+            .watch(&dir, notify::RecursiveMode::NonRecursive)
+            .is_err()
+        {
+            return;
+        }
+        while watch_receiver.recv().is_ok() {
+            _ = sender.send(Signal::Presets(load_presets(&dir)));
+        }
+    });
+}
+
+async fn handle_remote_connection(
+    stream: TcpStream,
+    sender: mpsc::Sender<Signal>,
+    status: Arc<RwLock<RemoteStatus>>,
+) {
+    let mut writer = stream.clone();
+    let mut lines = async_std::io::BufReader::new(stream).lines();
+    while let Some(Ok(line)) = lines.next().await {
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str::<RemoteCommand>(&line) {
+            Ok(command) => {
+                let (ack, ack_receiver) = futures::channel::oneshot::channel::<()>();
+                _ = sender.send(Signal::Remote(command, ack));
+                _ = ack_receiver.await;
+            }
+            Err(e) => _ = sender.send(Signal::Error(format!("Error: {}", e))),
+        }
+        let reply = {
+            let status = status.read().unwrap();
+            serde_json::to_string(&*status).unwrap_or_default()
+        };
+        if writer.write_all(reply.as_bytes()).await.is_err()
+            || writer.write_all(b"\n").await.is_err()
+        {
+            break;
+        }
+    }
+}
+
 pub async fn get_bridge_ip() -> Result<IpAddr> {
     bridge::discover_nupnp()?
         .pop()
@@ -5,7 +5,7 @@ use app::SyncMode;
 
 fn main() {
     let native_options = eframe::NativeOptions {
-        initial_window_size: Some([400.0, 340.0].into()),
+        initial_window_size: Some([720.0, 480.0].into()),
         min_window_size: Some([300.0, 200.0].into()),
         follow_system_theme: true,
         ..Default::default()
@@ -16,6 +16,8 @@ fn main() {
         Box::new(|cc| {
             let mut app = Box::new(App::new(cc));
             app.get_bridge_ip();
+            app.start_presets();
+            app.start_remote_control();
             app
         }),
     );
@@ -40,6 +42,7 @@ fn label(sync_mode: SyncMode) -> &'static str {
         SyncMode::None => "none",
         SyncMode::Time => "time",
         SyncMode::TimeAndColor => "time & color",
+        SyncMode::Ambient => "ambient",
     }
 }
 
@@ -74,89 +77,207 @@ impl App {
         });
     }
 
-    fn draw_connected(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        egui::CentralPanel::default().show(ctx, |ui| {
-            if ui
-                .add(egui::Slider::new(&mut self.data.r.start, 0..=255).text("r min"))
-                .changed()
-            {
-                update_end(&mut self.data.r)
-            }
-            if ui
-                .add(egui::Slider::new(&mut self.data.r.end, 0..=255).text("r max"))
-                .changed()
-            {
-                update_start(&mut self.data.r)
-            }
+    fn draw_controls_tab(&mut self, ui: &mut egui::Ui) {
+        if ui
+            .add(egui::Slider::new(&mut self.data.r.start, 0..=255).text("r min"))
+            .changed()
+        {
+            update_end(&mut self.data.r)
+        }
+        if ui
+            .add(egui::Slider::new(&mut self.data.r.end, 0..=255).text("r max"))
+            .changed()
+        {
+            update_start(&mut self.data.r)
+        }
 
-            if ui
-                .add(egui::Slider::new(&mut self.data.g.start, 0..=255).text("g min"))
-                .changed()
-            {
-                update_end(&mut self.data.g)
-            }
-            if ui
-                .add(egui::Slider::new(&mut self.data.g.end, 0..=255).text("g max"))
-                .changed()
-            {
-                update_start(&mut self.data.g)
-            }
+        if ui
+            .add(egui::Slider::new(&mut self.data.g.start, 0..=255).text("g min"))
+            .changed()
+        {
+            update_end(&mut self.data.g)
+        }
+        if ui
+            .add(egui::Slider::new(&mut self.data.g.end, 0..=255).text("g max"))
+            .changed()
+        {
+            update_start(&mut self.data.g)
+        }
 
-            if ui
-                .add(egui::Slider::new(&mut self.data.b.start, 0..=255).text("b min"))
-                .changed()
-            {
-                update_end(&mut self.data.b)
-            }
-            if ui
-                .add(egui::Slider::new(&mut self.data.b.end, 0..=255).text("b max"))
-                .changed()
-            {
-                update_start(&mut self.data.b)
-            }
-            ui.add(egui::Separator::default());
-            if ui
-                .add(egui::Slider::new(&mut self.data.time.start, 1..=100).text("time (100ms) min"))
-                .changed()
-            {
-                update_end(&mut self.data.time)
-            }
+        if ui
+            .add(egui::Slider::new(&mut self.data.b.start, 0..=255).text("b min"))
+            .changed()
+        {
+            update_end(&mut self.data.b)
+        }
+        if ui
+            .add(egui::Slider::new(&mut self.data.b.end, 0..=255).text("b max"))
+            .changed()
+        {
+            update_start(&mut self.data.b)
+        }
+        ui.add(egui::Separator::default());
+        if ui
+            .add(egui::Slider::new(&mut self.data.time.start, 1..=100).text("time (100ms) min"))
+            .changed()
+        {
+            update_end(&mut self.data.time)
+        }
+        let clock_driven = self.sync_mode == SyncMode::None;
+        ui.add_enabled_ui(!clock_driven, |ui| {
             if ui
                 .add(egui::Slider::new(&mut self.data.time.end, 1..=100).text("time (100ms) max"))
                 .changed()
             {
                 update_start(&mut self.data.time)
             }
-            ui.add(egui::Checkbox::new(&mut self.data.fade, "fade"));
-
-            egui::ComboBox::from_label("sync mode")
-                .selected_text(label(self.sync_mode))
-                .show_ui(ui, |ui| {
-                    ui.selectable_value(&mut self.sync_mode, SyncMode::None, label(SyncMode::None));
-                    ui.selectable_value(&mut self.sync_mode, SyncMode::Time, label(SyncMode::Time));
-                    ui.selectable_value(
-                        &mut self.sync_mode,
-                        SyncMode::TimeAndColor,
-                        label(SyncMode::TimeAndColor),
-                    );
-                });
+        });
+        if clock_driven {
+            ui.label(
+                "time max only affects fade length in \"none\" mode; time min sets the clock rate",
+            );
+        }
+        ui.add(egui::Checkbox::new(&mut self.data.fade, "fade"));
+
+        egui::ComboBox::from_label("sync mode")
+            .selected_text(label(self.sync_mode))
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut self.sync_mode, SyncMode::None, label(SyncMode::None));
+                ui.selectable_value(&mut self.sync_mode, SyncMode::Time, label(SyncMode::Time));
+                ui.selectable_value(
+                    &mut self.sync_mode,
+                    SyncMode::TimeAndColor,
+                    label(SyncMode::TimeAndColor),
+                );
+                ui.selectable_value(
+                    &mut self.sync_mode,
+                    SyncMode::Ambient,
+                    label(SyncMode::Ambient),
+                );
+            });
 
-            ui.add(egui::Separator::default());
+        ui.add(egui::Separator::default());
 
-            egui::ScrollArea::vertical().show(ui, |ui| {
-                for (_, light) in &mut self.lights {
+        egui::ComboBox::from_label("preset")
+            .selected_text(
+                self.selected_preset
+                    .as_ref()
+                    .and_then(|name| self.presets.iter().find(|preset| &preset.name == name))
+                    .map_or("none".to_string(), |preset| preset.display_name.clone()),
+            )
+            .show_ui(ui, |ui| {
+                for preset in self.presets.clone() {
+                    if ui
+                        .selectable_value(
+                            &mut self.selected_preset,
+                            Some(preset.name.clone()),
+                            &preset.display_name,
+                        )
+                        .clicked()
+                    {
+                        self.data = preset.data.clone();
+                        self.sync_mode = preset.sync_mode;
+                        self.rebuild_modulators = true;
+                    }
+                }
+            });
+
+        ui.horizontal(|ui| {
+            ui.add(egui::TextEdit::singleline(&mut self.new_preset_name).hint_text("preset name"));
+            if ui.add(egui::Button::new("Save as…")).clicked() && !self.new_preset_name.is_empty()
+            {
+                self.save_preset(self.new_preset_name.clone());
+            }
+            if ui.add(egui::Button::new("Delete")).clicked() {
+                if let Some(name) = self.selected_preset.clone() {
+                    self.delete_preset(&name);
+                }
+            }
+        });
+    }
+
+    fn draw_lights_tab(&mut self, ui: &mut egui::Ui) {
+        let sync_mode = self.sync_mode;
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            for (_, light) in &mut self.lights {
+                ui.horizontal(|ui| {
                     if ui
                         .add(egui::Checkbox::new(&mut light.on, light.light.name.clone()))
                         .changed()
                     {
                         self.rebuild_modulators = true;
                     }
+                    if sync_mode == SyncMode::None
+                        && ui
+                            .add(
+                                egui::DragValue::new(&mut light.beat_division)
+                                    .clamp_range(1..=16)
+                                    .prefix("beat / "),
+                            )
+                            .changed()
+                    {
+                        self.rebuild_modulators = true;
+                    }
+                });
+            }
+
+            ui.allocate_space(ui.available_size());
+        });
+    }
+
+    fn draw_preview_tab(&mut self, ui: &mut egui::Ui) {
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            for (_, light) in &self.lights {
+                if !light.on {
+                    continue;
                 }
+                ui.horizontal(|ui| {
+                    let (r, g, b) = light.last_color.unwrap_or((0, 0, 0));
+                    let (rect, _) =
+                        ui.allocate_exact_size(egui::vec2(24.0, 24.0), egui::Sense::hover());
+                    ui.painter()
+                        .rect_filled(rect, 2.0, egui::Color32::from_rgb(r, g, b));
+                    ui.add(egui::Label::new(light.light.name.clone()));
+                });
+            }
 
-                ui.allocate_space(ui.available_size());
-            });
+            ui.allocate_space(ui.available_size());
         });
     }
+
+    fn draw_connected(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        if let Some(error) = self.error.clone() {
+            egui::TopBottomPanel::top("error").show(ctx, |ui| {
+                ui.add(egui::Label::new(error));
+            });
+        }
+
+        let mut dock_state =
+            std::mem::replace(&mut self.dock_state, egui_dock::DockState::new(vec![]));
+        egui_dock::DockArea::new(&mut dock_state).show(ctx, &mut TabViewer { app: self });
+        self.dock_state = dock_state;
+    }
+}
+
+struct TabViewer<'a> {
+    app: &'a mut App,
+}
+
+impl egui_dock::TabViewer for TabViewer<'_> {
+    type Tab = String;
+
+    fn title(&mut self, tab: &mut Self::Tab) -> egui::WidgetText {
+        tab.as_str().into()
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui, tab: &mut Self::Tab) {
+        match tab.as_str() {
+            "Controls" => self.app.draw_controls_tab(ui),
+            "Lights" => self.app.draw_lights_tab(ui),
+            "Preview" => self.app.draw_preview_tab(ui),
+            _ => {}
+        }
+    }
 }
 
 impl eframe::App for App {